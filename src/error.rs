@@ -1,25 +1,34 @@
 use thiserror::Error;
 
+/// A byte-offset range into the original expression string that an error relates to, for
+/// highlighting the offending text (e.g. in an editor or CLI diagnostic).
+pub type Span = std::ops::Range<usize>;
+
 #[derive(Error, Debug)]
 pub enum BoolExprParseError {
     #[error("found invalid brackets, only parentheses are supported in boolean expressions")]
-    InvalidBrackets,
+    InvalidBrackets { span: Span },
     #[error("found non-alphanumeric token in boolean expression: '{token}'")]
-    NonAlphanumericToken { token: String },
+    NonAlphanumericToken { token: String, span: Span },
     #[error("found consecutive terminals in boolean expression (expected operator between them), second was: '{second:?}'")]
     ConsecutiveTerminals {
         second: boolean_expression::Expr<String>,
+        span: Span,
     },
     #[error("found consecutive operators in boolean expression (expected terminal between them), second was: '{second}'")]
-    ConsecutiveOperators { second: String },
+    ConsecutiveOperators { second: String, span: Span },
     #[error("too many nested bracketed expressions found in boolean expression, please simplify your expression (this is a security measure)")]
-    TooMuchNesting,
+    TooMuchNesting { span: Span },
     #[error("found trailing operator '{op}' at end of boolean expression, expected terminal")]
-    TrailingOperator { op: String },
+    TrailingOperator { op: String, span: Span },
     #[error("found empty stack in boolean expression (either empty parentheses or a completely empty expression)")]
-    EmptyStack,
+    EmptyStack { span: Span },
     #[error("found unmatched bracket in boolean expression: '{expr}'")]
-    UnmatchedBracket { expr: String },
+    UnmatchedBracket { expr: String, span: Span },
     #[error("found terminal '{terminal}', which was not in list of allowed terminals in boolean expression")]
-    UnknownTerminal { terminal: String },
+    UnknownTerminal { terminal: String, span: Span },
+    #[error("found boolean constant '{value}' in boolean expression, which is rejected by the `NO_CONSTANTS` restriction")]
+    RestrictedConstant { value: bool, span: Span },
+    #[error("operator precedence would silently decide associativity here, which is rejected by the `REQUIRE_EXPLICIT_PARENS` restriction; add explicit parentheses")]
+    AmbiguousPrecedence { span: Span },
 }
@@ -1,9 +1,16 @@
 mod error;
 mod format;
 mod parser;
+mod restrictions;
 #[cfg(test)]
 mod tests;
+mod truth_table;
 
-pub use error::BoolExprParseError;
+pub use error::{BoolExprParseError, Span};
 pub use format::format_bool_expr;
-pub use parser::{parse_bool_expr_str, parse_bool_expr_str_with_max_nesting};
+pub use parser::{
+    parse_bool_expr_str, parse_bool_expr_str_collecting, parse_bool_expr_str_with_max_nesting,
+    parse_bool_expr_str_with_options,
+};
+pub use restrictions::Restrictions;
+pub use truth_table::{format_truth_table, truth_table, TruthTable, TruthTableError, TruthTableRow};
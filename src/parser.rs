@@ -1,4 +1,5 @@
-use super::error::BoolExprParseError;
+use super::error::{BoolExprParseError, Span};
+use super::restrictions::Restrictions;
 use boolean_expression::Expr;
 
 /// Parses the given string into a boolean expression.
@@ -14,206 +15,591 @@ pub fn parse_bool_expr_str(
 
 /// Parses the given string into a boolean expression.
 ///
-/// A maximum degree of nesting must be set in order to prevent an attacker from causing excessive
-/// memory use through infinite bracketing. Note that this would *not* trigger a stack overflow,
-/// it would trigger an out-of-memory error, eventually, after significant stagnation. A default value
-/// can be used for simplicity (and brevity) with `parse_bool_expr_str`
+/// A maximum degree of nesting must be set in order to prevent an attacker from causing a stack
+/// overflow through infinite bracketing or negation. A default value can be used for simplicity
+/// (and brevity) with `parse_bool_expr_str`
+///
+/// This fails on the first problem found. To gather every problem in the expression in one pass,
+/// use [`parse_bool_expr_str_collecting`]. To additionally place restrictions on what counts as a
+/// valid expression, use [`parse_bool_expr_str_with_options`].
 pub fn parse_bool_expr_str_with_max_nesting(
     raw_expr_str: &str,
     allowed_terminals: &[&str],
     max_nesting: usize,
 ) -> Result<Expr<String>, BoolExprParseError> {
-    // Replace logic operators to make everything uniform: `&` for and, `|` for or, and `!`
-    // for not. Also add space around brackets so they can be treated as independent tokens.
-    // Weird spacing here to avoid messing up terminal names.
-    let expr_str = raw_expr_str
-        .to_lowercase()
-        .replace(" and", " &")
-        .replace(" &&", " &")
-        .replace(" or", " |")
-        .replace(" ||", " |")
-        .replace(" not ", " !")
-        .replace("(", " ( ")
-        .replace(")", " ) ");
-    // We haven't handled `not` at the start of the expression
-    let expr_str = if expr_str.starts_with("not ") {
-        format!("!{}", &expr_str[3..])
+    parse_bool_expr_str_with_options(
+        raw_expr_str,
+        allowed_terminals,
+        max_nesting,
+        Restrictions::empty(),
+    )
+}
+
+/// Parses the given string into a boolean expression, rejecting anything that violates the given
+/// [`Restrictions`] (e.g. literal constants, or operator precedence silently deciding
+/// associativity), in addition to the usual parsing rules.
+///
+/// This fails on the first problem found, as for [`parse_bool_expr_str_with_max_nesting`].
+pub fn parse_bool_expr_str_with_options(
+    raw_expr_str: &str,
+    allowed_terminals: &[&str],
+    max_nesting: usize,
+    restrictions: Restrictions,
+) -> Result<Expr<String>, BoolExprParseError> {
+    let (expr, mut errors) = run_parser(raw_expr_str, allowed_terminals, max_nesting, restrictions);
+    if errors.is_empty() {
+        Ok(expr.simplify_via_laws())
+    } else {
+        Err(errors.remove(0))
+    }
+}
+
+/// Parses the given string into a boolean expression, recovering from problems rather than
+/// stopping at the first one, so every distinct problem in the expression can be reported in a
+/// single pass (much like a mature compiler front-end reports a full batch of diagnostics rather
+/// than making the user fix and reparse one mistake at a time).
+///
+/// Recoverable mistakes are repaired as they're found so that parsing can keep going:
+/// consecutive terminals (e.g. `x y`) have an implicit `&` inserted between them, consecutive
+/// operators (e.g. `x & & y`) have the stray operator skipped, and a missing closing bracket is
+/// treated as though it were present. Each such repair is still recorded as an error. If no
+/// errors were found, the resulting expression is returned; otherwise every error gathered along
+/// the way is returned, in the order they were encountered.
+pub fn parse_bool_expr_str_collecting(
+    raw_expr_str: &str,
+    allowed_terminals: &[&str],
+    max_nesting: usize,
+) -> Result<Expr<String>, Vec<BoolExprParseError>> {
+    let (expr, errors) = run_parser(
+        raw_expr_str,
+        allowed_terminals,
+        max_nesting,
+        Restrictions::empty(),
+    );
+    if errors.is_empty() {
+        Ok(expr.simplify_via_laws())
     } else {
-        expr_str
+        Err(errors)
+    }
+}
+
+/// Runs the tokeniser and recovering parser over `raw_expr_str`, shared by every public entry
+/// point above: they differ only in whether they stop at the first error ([`BoolExprParseError`])
+/// or return every one gathered (`Vec<BoolExprParseError>`), and in which `restrictions` apply.
+fn run_parser(
+    raw_expr_str: &str,
+    allowed_terminals: &[&str],
+    max_nesting: usize,
+    restrictions: Restrictions,
+) -> (Expr<String>, Vec<BoolExprParseError>) {
+    let tokens = tokenize(raw_expr_str, restrictions);
+
+    let mut parser = Parser {
+        tokens: &tokens,
+        pos: 0,
+        max_nesting,
+        allowed_terminals,
+        raw_expr_str,
+        restrictions,
+        errors: Vec::new(),
     };
+    let (mut expr, _) = parser.parse_expr(0, 1);
+
+    // Anything left over is either a stray closing bracket, or some other character that isn't
+    // the start of an atom (an opening bracket would already have been consumed, and a genuine
+    // atom would have been absorbed as an implicit `&` by the recovery in `parse_expr`). The
+    // latter case still needs full Pratt parsing, not a token-at-a-time walk, or a real operator
+    // stranded after the bad character (e.g. the `&` in `x @ y & z`) would be misread as another
+    // bad token instead of being honoured
+    while let Some(tok) = parser.peek() {
+        let span = tok.span.clone();
 
-    // Make sure we don't have illegal brackets
-    if expr_str.contains('[')
-        || expr_str.contains(']')
-        || expr_str.contains('{')
-        || expr_str.contains('}')
-        || expr_str.contains('<')
-        || expr_str.contains('>')
-    {
-        return Err(BoolExprParseError::InvalidBrackets);
+        if tok.text == ")" {
+            parser.pos += 1;
+            parser.errors.push(BoolExprParseError::UnmatchedBracket {
+                expr: raw_expr_str.to_string(),
+                span,
+            });
+            continue;
+        }
+
+        let (rhs, _) = parser.parse_expr(0, 1);
+        if !restrictions.contains(Restrictions::SINGLE_CHAR_TERMINALS) {
+            parser.errors.push(BoolExprParseError::ConsecutiveTerminals {
+                second: rhs.clone(),
+                span,
+            });
+        }
+        expr &= rhs;
     }
-    // Split things up into tokens (removing any double whitespace)
-    let tokens: Vec<&str> = expr_str.split(' ').map(|tok| tok.trim()).collect();
-
-    // Boolean expressions are built on unary negation operators and binary operations, together
-    // with brackets that start new sub-expressions. Hence, we accumulate non-bracketed expressions
-    // into the newest stack, and collapse them as brackets are closed
-    let mut stacks = vec![TokenStack::default()];
-    for tok in tokens {
-        // Make sure we haven't got too much nesting
-        if stacks.len() > max_nesting {
-            return Err(BoolExprParseError::TooMuchNesting);
+
+    (expr, parser.errors)
+}
+
+/// Tokenises the given expression string, tracking the byte span each token came from in the
+/// original string (so that errors can point back at the offending text) rather than mangling the
+/// string with substitutions the way earlier versions of this parser did. Keywords (`and`, `or`,
+/// `not`, `xor`, `implies`, `iff`) are always matched case-insensitively; identifiers are
+/// lower-cased too, unless `restrictions` contains [`Restrictions::CASE_SENSITIVE`]. If
+/// `restrictions` contains [`Restrictions::SINGLE_CHAR_TERMINALS`], every alphanumeric character
+/// outside a keyword becomes its own token rather than being grouped into words.
+fn tokenize(raw: &str, restrictions: Restrictions) -> Vec<Token> {
+    let chars: Vec<(usize, char)> = raw.char_indices().collect();
+    let len = chars.len();
+
+    // The byte offset one past the character at index `k` (or the end of the string, for the
+    // last character)
+    let end_of = |k: usize| -> usize {
+        if k + 1 < len {
+            chars[k + 1].0
+        } else {
+            raw.len()
         }
-        if tok.is_empty() {
+    };
+
+    let mut tokens = Vec::new();
+    let mut idx = 0;
+    while idx < len {
+        let (i, c) = chars[idx];
+        if c.is_whitespace() {
+            idx += 1;
             continue;
         }
 
-        match tok {
-            "(" => stacks.push(TokenStack::default()),
-            ")" => {
-                if stacks.len() > 1 {
-                    let unwound = stacks.remove(stacks.len() - 1);
-                    let new_last = stacks.last_mut().unwrap();
-                    new_last.push(unwound.finish()?)?;
-                } else {
-                    return Err(BoolExprParseError::UnmatchedBracket {
-                        expr: raw_expr_str.to_string(),
-                    });
+        // Multi-character operators are checked for explicitly before falling back to
+        // single-character tokens
+        if raw[i..].starts_with("<->") {
+            let end = if idx + 3 < len { chars[idx + 3].0 } else { raw.len() };
+            tokens.push(Token { text: "<->".to_string(), span: i..end });
+            idx += 3;
+            continue;
+        }
+        if raw[i..].starts_with("->") {
+            tokens.push(Token { text: "->".to_string(), span: i..end_of(idx + 1) });
+            idx += 2;
+            continue;
+        }
+        if (c == '&' || c == '|') && chars.get(idx + 1).map(|&(_, c2)| c2) == Some(c) {
+            tokens.push(Token { text: c.to_string(), span: i..end_of(idx + 1) });
+            idx += 2;
+            continue;
+        }
+
+        match c {
+            '(' | ')' | '&' | '|' | '^' | '!' | '[' | ']' | '{' | '}' | '<' | '>' | '-' => {
+                tokens.push(Token { text: c.to_string(), span: i..end_of(idx) });
+                idx += 1;
+            }
+            c if c.is_alphanumeric() || c == '_' => {
+                let start = i;
+                let mut j = idx;
+                while j < len && (chars[j].1.is_alphanumeric() || chars[j].1 == '_') {
+                    j += 1;
                 }
+                let end = if j < len { chars[j].0 } else { raw.len() };
+
+                let word_raw = &raw[start..end];
+                let word_lower = word_raw.to_lowercase();
+                // Keywords are recognised as whole words before `SINGLE_CHAR_TERMINALS` gets a
+                // chance to split the run up, so that e.g. `true` is still the constant `true`
+                // rather than the terminals `t`, `r`, `u` and `e`
+                if restrictions.contains(Restrictions::SINGLE_CHAR_TERMINALS)
+                    && !is_keyword(&word_lower)
+                {
+                    for &(char_pos, ch) in &chars[idx..j] {
+                        let text = if restrictions.contains(Restrictions::CASE_SENSITIVE) {
+                            ch.to_string()
+                        } else {
+                            ch.to_lowercase().to_string()
+                        };
+                        tokens.push(Token {
+                            text,
+                            span: char_pos..char_pos + ch.len_utf8(),
+                        });
+                    }
+                    idx = j;
+                    continue;
+                }
+
+                let text = match word_lower.as_str() {
+                    "and" => "&".to_string(),
+                    "or" => "|".to_string(),
+                    "not" => "!".to_string(),
+                    "xor" => "^".to_string(),
+                    "implies" => "->".to_string(),
+                    "iff" => "<->".to_string(),
+                    "true" => "true".to_string(),
+                    "false" => "false".to_string(),
+                    _ if restrictions.contains(Restrictions::CASE_SENSITIVE) => {
+                        word_raw.to_string()
+                    }
+                    _ => word_lower,
+                };
+                tokens.push(Token { text, span: start..end });
+                idx = j;
+            }
+            other => {
+                tokens.push(Token { text: other.to_string(), span: i..end_of(idx) });
+                idx += 1;
             }
-            "&" => stacks.last_mut().unwrap().push_op(BoolOp::And)?,
-            "|" => stacks.last_mut().unwrap().push_op(BoolOp::Or)?,
-            // We have to have support for constants, because they might be written back after logical
-            // simplification
-            "true" => stacks.last_mut().unwrap().push(Expr::Const(true))?,
-            "false" => stacks.last_mut().unwrap().push(Expr::Const(false))?,
-            tok => stacks
-                .last_mut()
-                .unwrap()
-                .push_str(tok, allowed_terminals)?,
         }
     }
 
-    // All sub-stacks should have been collapsed into the primary stack
-    if stacks.len() > 1 {
-        return Err(BoolExprParseError::UnmatchedBracket {
-            expr: raw_expr_str.to_string(),
-        });
+    tokens
+}
+
+/// Whether a lower-cased word is one of the keywords [`tokenize`] gives special meaning to,
+/// rather than a terminal name.
+fn is_keyword(word_lower: &str) -> bool {
+    matches!(
+        word_lower,
+        "and" | "or" | "not" | "xor" | "implies" | "iff" | "true" | "false"
+    )
+}
+
+/// A single token produced by [`tokenize`], alongside the byte span it came from in the original
+/// expression string.
+struct Token {
+    text: String,
+    span: Span,
+}
+
+/// Holds the mutable state of an in-progress parse: the token stream, our position in it, and
+/// the errors recovered from so far. This is a precedence-climbing (Pratt) parser: `!` binds as a
+/// high-precedence prefix operator, and the binary operators bind (loosest to tightest) `<->`,
+/// `->`, `|`, `^`, then `&`. Brackets start a fresh sub-parse, with `depth` tracking how many
+/// we're nested inside of to enforce `max_nesting`.
+struct Parser<'a> {
+    tokens: &'a [Token],
+    pos: usize,
+    max_nesting: usize,
+    allowed_terminals: &'a [&'a str],
+    raw_expr_str: &'a str,
+    restrictions: Restrictions,
+    errors: Vec<BoolExprParseError>,
+}
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
     }
-    // This will work provided there isn't a trailing operator
-    let expr = stacks.remove(0).finish()?;
-
-    // We'll automatically try to perform elementary simplification (cheap operation)
-    Ok(expr.simplify_via_laws())
-}
-
-/// Intermediate parsing infrastructure for tokens in boolean expressions.
-#[derive(Default)]
-struct TokenStack {
-    expr: Option<Expr<String>>,
-    op: Option<BoolOp>,
-}
-impl TokenStack {
-    /// Pushes the given new token string onto the stack. This is simply a wrapper for token parsing and
-    /// calling [`Self::push`].
-    fn push_str(
-        &mut self,
-        token: &str,
-        allowed_terminals: &[&str],
-    ) -> Result<(), BoolExprParseError> {
-        // Strip not modifiers
-        let (token, is_negated) = if token.starts_with('!') {
-            (&token[1..], true)
-        } else {
-            (token, false)
-        };
-        // Make sure the token is valid to avoid issues with stray modifiers
-        if token.chars().any(|c| !c.is_alphanumeric() && c != '_') {
-            return Err(BoolExprParseError::NonAlphanumericToken {
-                token: token.to_string(),
-            });
+
+    /// The span to blame for an error that occurs once we've run out of tokens: an empty span
+    /// just past the last token, or the whole string for an expression with no tokens at all
+    /// (empty, or only whitespace).
+    fn eof_span(&self) -> Span {
+        match self.tokens.last() {
+            Some(tok) => tok.span.end..tok.span.end,
+            None => 0..self.raw_expr_str.len(),
         }
+    }
 
-        // Make sure this is a legal terminal
-        if !allowed_terminals.contains(&token) {
-            return Err(BoolExprParseError::UnknownTerminal {
-                terminal: token.to_string(),
-            });
+    /// Parses a (sub-)expression, consuming operators whose left binding power is at least
+    /// `min_bp`. A fresh call with a higher `min_bp` is used for the right-hand side of an
+    /// operator, making it bind only as tightly as that operator allows.
+    ///
+    /// Alongside the expression, returns the single operator that combined it at this call's own
+    /// level, if any (`None` for a bare atom, a negation, or a bracketed sub-expression, since
+    /// those are unambiguous regardless of what's inside). This is used to detect, under
+    /// [`Restrictions::REQUIRE_EXPLICIT_PARENS`], when two different operators have been combined
+    /// without explicit brackets to say which binds first.
+    fn parse_expr(&mut self, min_bp: u8, depth: usize) -> (Expr<String>, Option<BoolOp>) {
+        let mut lhs = self.parse_prefix(depth);
+        let mut lhs_op: Option<BoolOp> = None;
+
+        while let Some(tok) = self.peek() {
+            let (text, span) = (tok.text.clone(), tok.span.clone());
+
+            let (op, lbp, rbp) = match op_binding_power(&text) {
+                Some(op) => op,
+                // Not an operator. If it looks like the start of another atom, the user is
+                // missing an operator between two terminals; recover by inserting an implicit
+                // `&`, at `&`'s own binding power, and keep going
+                None if starts_atom(&text) && min_bp <= AND_LBP => {
+                    let (rhs, _) = self.parse_expr(AND_RBP, depth);
+                    // Under `SINGLE_CHAR_TERMINALS`, adjacent terminals joined by an implicit `&`
+                    // are the intended way to write a conjunction (e.g. `abc` for `a & b & c`),
+                    // not a mistake
+                    if !self.restrictions.contains(Restrictions::SINGLE_CHAR_TERMINALS) {
+                        self.errors.push(BoolExprParseError::ConsecutiveTerminals {
+                            second: rhs.clone(),
+                            span,
+                        });
+                    }
+                    lhs &= rhs;
+                    lhs_op = Some(BoolOp::And);
+                    continue;
+                }
+                None => break,
+            };
+            if lbp < min_bp {
+                break;
+            }
+
+            if self.restrictions.contains(Restrictions::REQUIRE_EXPLICIT_PARENS) {
+                if let Some(prev) = lhs_op {
+                    if prev != op {
+                        self.errors.push(BoolExprParseError::AmbiguousPrecedence {
+                            span: span.clone(),
+                        });
+                    }
+                }
+            }
+
+            self.pos += 1;
+
+            if self.peek().is_none() {
+                self.errors.push(BoolExprParseError::TrailingOperator {
+                    op: op.into(),
+                    span,
+                });
+                // Recover by treating the missing right-hand side as `true`, so the expression
+                // stays well-formed
+                lhs = combine(op, lhs, Expr::Const(true));
+                break;
+            }
+
+            let (rhs, rhs_op) = self.parse_expr(rbp, depth);
+            if self.restrictions.contains(Restrictions::REQUIRE_EXPLICIT_PARENS) {
+                if let Some(k) = rhs_op {
+                    if k != op {
+                        self.errors
+                            .push(BoolExprParseError::AmbiguousPrecedence { span });
+                    }
+                }
+            }
+            lhs = combine(op, lhs, rhs);
+            lhs_op = Some(op);
         }
 
-        // It is certain that `self.right` is `None`
-        let right_expr = if is_negated {
-            Expr::Not(Box::new(Expr::Terminal(token.to_string())))
-        } else {
-            Expr::Terminal(token.to_string())
+        (lhs, lhs_op)
+    }
+
+    /// Parses a single prefix/atom: a terminal, a constant, a `!`-negated sub-expression, or a
+    /// bracketed sub-expression (which recurses back into [`Self::parse_expr`]).
+    fn parse_prefix(&mut self, depth: usize) -> Expr<String> {
+        let (text, span) = match self.peek() {
+            Some(tok) => (tok.text.clone(), tok.span.clone()),
+            None => {
+                self.errors.push(BoolExprParseError::EmptyStack {
+                    span: self.eof_span(),
+                });
+                return Expr::Const(true);
+            }
         };
 
-        self.push(right_expr)
-    }
-    /// Pushes the given expression onto the stack. This will fail if the stack has not had an operator
-    /// pushed onto it (provided the stack is non-empty, otherwise this will just become the first
-    /// element).
-    fn push(&mut self, right: Expr<String>) -> Result<(), BoolExprParseError> {
-        if self.expr.is_none() {
-            self.expr = Some(right);
-
-            Ok(())
-        } else if self.op.is_none() {
-            Err(BoolExprParseError::ConsecutiveTerminals { second: right })
-        } else {
-            // We have a left expression and an operator; we definitely don't have a right expression,
-            // because we automatically combine it in this function
-            self.expr = Some(match self.op {
-                // If there is no left expression, `true & x = x`
-                Some(BoolOp::And) => {
-                    std::mem::take(&mut self.expr).unwrap_or(Expr::Const(true)) & right
+        match text.as_str() {
+            "!" => {
+                self.pos += 1;
+                let depth = depth + 1;
+
+                if depth > self.max_nesting {
+                    self.errors.push(BoolExprParseError::TooMuchNesting { span });
+                    // Don't recurse any further into the over-nested negation; just skip past any
+                    // more `!`s stacked on top of it so parsing can continue after them
+                    while matches!(self.peek(), Some(tok) if tok.text == "!") {
+                        self.pos += 1;
+                    }
+                    return Expr::Const(true);
                 }
-                // If there is no left expression `false | y = y`
-                Some(BoolOp::Or) => {
-                    std::mem::take(&mut self.expr).unwrap_or(Expr::Const(false)) | right
+
+                // `!` binds tighter than every binary operator, so its operand is parsed with
+                // the highest binding power in use
+                let (inner, _) = self.parse_expr(NOT_BP, depth);
+                Expr::Not(Box::new(inner))
+            }
+            "(" => {
+                self.pos += 1;
+                let depth = depth + 1;
+
+                if depth > self.max_nesting {
+                    self.errors.push(BoolExprParseError::TooMuchNesting { span });
+                    // Don't recurse any further into the over-nested bracket; just skip past its
+                    // matching close so parsing can continue after it
+                    self.skip_bracketed_tokens();
+                    return Expr::Const(true);
                 }
-                None => return Err(BoolExprParseError::ConsecutiveTerminals { second: right }),
-            });
-            self.op = None;
 
-            Ok(())
+                let (inner, _) = self.parse_expr(0, depth);
+                let closed = matches!(self.peek(), Some(tok) if tok.text == ")");
+                if closed {
+                    self.pos += 1;
+                } else {
+                    // Treat the missing closing bracket as though it were present
+                    self.errors.push(BoolExprParseError::UnmatchedBracket {
+                        expr: self.raw_expr_str.to_string(),
+                        span,
+                    });
+                }
+                inner
+            }
+            ")" if depth > 1 => {
+                // Inside a bracket or negation, a `)` where an atom was expected means nothing
+                // usable came before it (e.g. `()`, `(x &)` or `(!)`); report the missing atom and
+                // leave the `)` for the enclosing bracket (or the top level) to deal with
+                self.errors.push(BoolExprParseError::EmptyStack { span });
+                Expr::Const(true)
+            }
+            ")" => {
+                // A stray closing bracket with nothing to close; skip it and keep looking for
+                // the atom we actually expected
+                self.errors.push(BoolExprParseError::UnmatchedBracket {
+                    expr: self.raw_expr_str.to_string(),
+                    span,
+                });
+                self.pos += 1;
+                self.parse_prefix(depth)
+            }
+            "&" | "|" | "^" | "->" | "<->" => {
+                // A stray operator with nothing before it; skip it and keep looking for the atom
+                // we actually expected
+                let (op, ..) = op_binding_power(&text).unwrap();
+                self.errors.push(BoolExprParseError::ConsecutiveOperators {
+                    second: op.into(),
+                    span,
+                });
+                self.pos += 1;
+                self.parse_prefix(depth)
+            }
+            "[" | "]" | "{" | "}" => {
+                self.errors.push(BoolExprParseError::InvalidBrackets { span });
+                self.pos += 1;
+                self.parse_prefix(depth)
+            }
+            "true" => {
+                self.pos += 1;
+                if self.restrictions.contains(Restrictions::NO_CONSTANTS) {
+                    self.errors
+                        .push(BoolExprParseError::RestrictedConstant { value: true, span });
+                }
+                Expr::Const(true)
+            }
+            "false" => {
+                self.pos += 1;
+                if self.restrictions.contains(Restrictions::NO_CONSTANTS) {
+                    self.errors
+                        .push(BoolExprParseError::RestrictedConstant { value: false, span });
+                }
+                Expr::Const(false)
+            }
+            _ => {
+                self.pos += 1;
+                self.parse_terminal(&text, span)
+            }
         }
     }
-    /// Pushes the given operator onto the stack, provided the stack is non-empty.
-    fn push_op(&mut self, op: BoolOp) -> Result<(), BoolExprParseError> {
-        if self.op.is_some() {
-            // After combination, this could still happen if there was no right expression, meaning
-            // we have consecutive operators
-            Err(BoolExprParseError::ConsecutiveOperators { second: op.into() })
-        } else {
-            // We know `self.right` is `None` by how operators are pushed
-            self.op = Some(op);
-            Ok(())
+
+    /// Parses a single terminal token.
+    fn parse_terminal(&mut self, name: &str, span: Span) -> Expr<String> {
+        // Make sure the token is valid to avoid issues with stray characters
+        if name.chars().any(|c| !c.is_alphanumeric() && c != '_') {
+            self.errors.push(BoolExprParseError::NonAlphanumericToken {
+                token: name.to_string(),
+                span,
+            });
+            return Expr::Const(true);
+        }
+
+        // Make sure this is a legal terminal
+        if !self.allowed_terminals.contains(&name) {
+            self.errors.push(BoolExprParseError::UnknownTerminal {
+                terminal: name.to_string(),
+                span,
+            });
         }
+
+        Expr::Terminal(name.to_string())
     }
-    /// Finalises the token stack and converts it into a final expression. This will fail if there
-    /// is an operator without a right expression to combine it with. This will also fail if the stack
-    /// is empty
-    fn finish(self) -> Result<Expr<String>, BoolExprParseError> {
-        if let Some(op) = self.op {
-            Err(BoolExprParseError::TrailingOperator { op: op.into() })
-        } else if let Some(expr) = self.expr {
-            Ok(expr)
-        } else {
-            Err(BoolExprParseError::EmptyStack)
+
+    /// Advances past the tokens making up an over-nested bracketed sub-expression, up to and
+    /// including its matching close, without recursing into them (the point of `max_nesting` is
+    /// to bound work, so recovery here must stay linear).
+    fn skip_bracketed_tokens(&mut self) {
+        let mut depth = 1;
+        while let Some(tok) = self.peek() {
+            match tok.text.as_str() {
+                "(" => depth += 1,
+                ")" => {
+                    depth -= 1;
+                    if depth == 0 {
+                        self.pos += 1;
+                        return;
+                    }
+                }
+                _ => {}
+            }
+            self.pos += 1;
         }
     }
 }
 
+/// Whether the given token text could begin a new atom (terminal, constant, negation or bracket),
+/// used to detect consecutive terminals during recovery.
+fn starts_atom(text: &str) -> bool {
+    matches!(text, "(" | "!" | "true" | "false")
+        || text
+            .chars()
+            .next()
+            .is_some_and(|c| c.is_alphanumeric() || c == '_')
+}
+
+const IFF_LBP: u8 = 1;
+const IFF_RBP: u8 = 2;
+const IMPLIES_LBP: u8 = 3;
+const IMPLIES_RBP: u8 = 4;
+const OR_LBP: u8 = 5;
+const OR_RBP: u8 = 6;
+const XOR_LBP: u8 = 7;
+const XOR_RBP: u8 = 8;
+const AND_LBP: u8 = 9;
+const AND_RBP: u8 = 10;
+const NOT_BP: u8 = 11;
+
+/// Looks up the operator, and its left/right binding powers, for a given token's text.
+fn op_binding_power(text: &str) -> Option<(BoolOp, u8, u8)> {
+    match text {
+        "<->" => Some((BoolOp::Iff, IFF_LBP, IFF_RBP)),
+        "->" => Some((BoolOp::Implies, IMPLIES_LBP, IMPLIES_RBP)),
+        "|" => Some((BoolOp::Or, OR_LBP, OR_RBP)),
+        "^" => Some((BoolOp::Xor, XOR_LBP, XOR_RBP)),
+        "&" => Some((BoolOp::And, AND_LBP, AND_RBP)),
+        _ => None,
+    }
+}
+
+/// Combines two expressions with the given operator. `^`, `->` and `<->` aren't primitives in
+/// `Expr`, so they're desugared into `&`/`|`/`!` here; the top-level caller runs
+/// `simplify_via_laws` over the final expression, which cleans up the inflation this causes.
+fn combine(op: BoolOp, lhs: Expr<String>, rhs: Expr<String>) -> Expr<String> {
+    match op {
+        BoolOp::And => lhs & rhs,
+        BoolOp::Or => lhs | rhs,
+        BoolOp::Xor => (lhs.clone() & negate(rhs.clone())) | (negate(lhs) & rhs),
+        BoolOp::Implies => negate(lhs) | rhs,
+        BoolOp::Iff => (lhs.clone() & rhs.clone()) | (negate(lhs) & negate(rhs)),
+    }
+}
+
+/// Wraps the given expression in a negation.
+fn negate(expr: Expr<String>) -> Expr<String> {
+    Expr::Not(Box::new(expr))
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
 enum BoolOp {
     And,
     Or,
+    Xor,
+    Implies,
+    Iff,
 }
-impl Into<String> for BoolOp {
-    fn into(self) -> String {
-        match self {
-            Self::And => "and",
-            Self::Or => "or",
+impl From<BoolOp> for String {
+    fn from(op: BoolOp) -> Self {
+        match op {
+            BoolOp::And => "and",
+            BoolOp::Or => "or",
+            BoolOp::Xor => "xor",
+            BoolOp::Implies => "implies",
+            BoolOp::Iff => "iff",
         }
         .to_string()
     }
@@ -0,0 +1,23 @@
+use bitflags::bitflags;
+
+bitflags! {
+    /// Restrictions that can be placed on what [`crate::parse_bool_expr_str_with_options`] will
+    /// accept, for callers that need to constrain user input beyond the usual `allowed_terminals`
+    /// list.
+    #[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+    pub struct Restrictions: u8 {
+        /// Reject literal `true`/`false` constants in user input. These are always accepted
+        /// internally (e.g. when reparsing a formatted expression), since the library itself
+        /// emits them after simplification.
+        const NO_CONSTANTS = 1 << 0;
+        /// Error whenever operator precedence would otherwise silently decide how an expression
+        /// associates, forcing the user to disambiguate with explicit parentheses instead.
+        const REQUIRE_EXPLICIT_PARENS = 1 << 1;
+        /// Treat each alphanumeric character as its own terminal, à la Rosetta Code's
+        /// single-character variable convention, so `abc` means `a & b & c`.
+        const SINGLE_CHAR_TERMINALS = 1 << 2;
+        /// Don't lower-case terminal names before parsing. Without this, mixed-case terminal
+        /// names are silently corrupted to lowercase.
+        const CASE_SENSITIVE = 1 << 3;
+    }
+}
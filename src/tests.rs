@@ -1,4 +1,8 @@
-use super::{format_bool_expr, parse_bool_expr_str};
+use super::{
+    format_bool_expr, format_truth_table, parse_bool_expr_str, parse_bool_expr_str_collecting,
+    parse_bool_expr_str_with_max_nesting, parse_bool_expr_str_with_options, truth_table,
+    BoolExprParseError, Restrictions,
+};
 
 #[test]
 fn should_parse_simple_expr() {
@@ -32,8 +36,184 @@ fn should_work_for_constants() {
     assert_eq!(format_bool_expr(parsed), "true");
 }
 
+#[test]
+fn should_respect_operator_precedence() {
+    // `&` should bind tighter than `|`, so this is `x | (y & z)`, not `(x | y) & z`
+    let raw = "x | y & z";
+    let parsed = parse_bool_expr_str(raw, &["x", "y", "z"]).unwrap();
+
+    assert_eq!(format_bool_expr(parsed), "x | (y & z)");
+}
+
+#[test]
+fn should_desugar_xor_implies_and_iff() {
+    let parsed = parse_bool_expr_str("x xor y", &["x", "y"]).unwrap();
+    assert_eq!(format_bool_expr(parsed), "(x & !y) | (!x & y)");
+
+    let parsed = parse_bool_expr_str("x -> y", &["x", "y"]).unwrap();
+    assert_eq!(format_bool_expr(parsed), "!x | y");
+
+    let parsed = parse_bool_expr_str("x <-> y", &["x", "y"]).unwrap();
+    assert_eq!(format_bool_expr(parsed), "(x & y) | (!x & !y)");
+
+    // Word forms and symbols are interchangeable
+    let from_words = parse_bool_expr_str("x implies y", &["x", "y"]).unwrap();
+    let from_symbol = parse_bool_expr_str("x -> y", &["x", "y"]).unwrap();
+    assert_eq!(format_bool_expr(from_words), format_bool_expr(from_symbol));
+}
+
+#[test]
+fn should_collect_multiple_errors_with_spans() {
+    // `w` is unknown, and `x y` is missing an operator between them
+    let raw = "w & x y";
+    let errors = parse_bool_expr_str_collecting(raw, &["x", "y"], 100).unwrap_err();
+
+    assert_eq!(errors.len(), 2);
+    assert!(matches!(
+        &errors[0],
+        BoolExprParseError::UnknownTerminal { terminal, span } if terminal == "w" && raw[span.clone()] == *"w"
+    ));
+    assert!(matches!(
+        &errors[1],
+        BoolExprParseError::ConsecutiveTerminals { span, .. } if raw[span.clone()] == *"y"
+    ));
+}
+
+#[test]
+fn should_recover_through_invalid_tokens_with_real_operators_after_them() {
+    // The `&` after the stray `@` must still be honoured as a real operator, rather than being
+    // misread as a further bad token
+    let raw = "x @ y & z";
+    let errors = parse_bool_expr_str_collecting(raw, &["x", "y", "z"], 100).unwrap_err();
+
+    assert!(matches!(
+        &errors[0],
+        BoolExprParseError::NonAlphanumericToken { token, span } if token == "@" && raw[span.clone()] == *"@"
+    ));
+    assert!(errors
+        .iter()
+        .all(|err| !matches!(err, BoolExprParseError::NonAlphanumericToken { token, .. } if token == "&")));
+}
+
+#[test]
+fn should_report_invalid_brackets_regardless_of_position() {
+    // `[` is invalid wherever it appears, not just when it's the very first token
+    let leading = parse_bool_expr_str_collecting("[x", &["x"], 100).unwrap_err();
+    assert!(matches!(
+        leading[..],
+        [BoolExprParseError::InvalidBrackets { .. }]
+    ));
+
+    let trailing = parse_bool_expr_str_collecting("x[y", &["x", "y"], 100).unwrap_err();
+    assert!(trailing
+        .iter()
+        .any(|err| matches!(err, BoolExprParseError::InvalidBrackets { .. })));
+}
+
 #[test]
 fn should_fail_on_unknown_terminals() {
     let raw = "x | y";
     assert!(parse_bool_expr_str(raw, &["y"]).is_err());
 }
+
+#[test]
+fn should_generate_truth_table() {
+    let raw = "x & y";
+    let parsed = parse_bool_expr_str(raw, &["x", "y"]).unwrap();
+    let table = truth_table(&parsed, &["x", "y"]).unwrap();
+
+    assert_eq!(table.terminals, vec!["x".to_string(), "y".to_string()]);
+    assert_eq!(table.rows.len(), 4);
+    assert_eq!(table.rows[0].assignment, vec![false, false]);
+    assert!(!table.rows[0].result);
+    assert_eq!(table.rows[3].assignment, vec![true, true]);
+    assert!(table.rows[3].result);
+
+    let rendered = format_truth_table(&table);
+    assert!(rendered.starts_with("x | y | x & y\n"));
+}
+
+#[test]
+fn should_fail_truth_table_on_missing_terminal() {
+    let raw = "x & y";
+    let parsed = parse_bool_expr_str(raw, &["x", "y"]).unwrap();
+
+    assert!(truth_table(&parsed, &["x"]).is_err());
+}
+
+#[test]
+fn should_fail_truth_table_with_too_many_terminals() {
+    let parsed = parse_bool_expr_str("x", &["x"]).unwrap();
+    let many: Vec<String> = (0..30).map(|i| format!("t{i}")).collect();
+    let terminals: Vec<&str> = many.iter().map(String::as_str).collect();
+
+    assert!(truth_table(&parsed, &terminals).is_err());
+}
+
+#[test]
+fn should_bound_negation_nesting() {
+    // Each `!` recurses just as a bracket does, so a long run of them must also be caught by
+    // `max_nesting` instead of overflowing the stack
+    let raw = format!("{}x", "!".repeat(1_000));
+    let err = parse_bool_expr_str_with_max_nesting(&raw, &["x"], 100).unwrap_err();
+    assert!(matches!(err, BoolExprParseError::TooMuchNesting { .. }));
+}
+
+#[test]
+fn should_respect_restrictions() {
+    // `NO_CONSTANTS` rejects literal constants in user input
+    let err = parse_bool_expr_str_with_options("x | true", &["x"], 100, Restrictions::NO_CONSTANTS)
+        .unwrap_err();
+    assert!(matches!(
+        err,
+        BoolExprParseError::RestrictedConstant { value: true, .. }
+    ));
+
+    // `REQUIRE_EXPLICIT_PARENS` rejects mixing operators without brackets to say which binds first
+    let err = parse_bool_expr_str_with_options(
+        "x | y & z",
+        &["x", "y", "z"],
+        100,
+        Restrictions::REQUIRE_EXPLICIT_PARENS,
+    )
+    .unwrap_err();
+    assert!(matches!(err, BoolExprParseError::AmbiguousPrecedence { .. }));
+    assert!(parse_bool_expr_str_with_options(
+        "x | (y & z)",
+        &["x", "y", "z"],
+        100,
+        Restrictions::REQUIRE_EXPLICIT_PARENS,
+    )
+    .is_ok());
+
+    // `SINGLE_CHAR_TERMINALS` treats each character as its own terminal
+    let parsed = parse_bool_expr_str_with_options(
+        "abc",
+        &["a", "b", "c"],
+        100,
+        Restrictions::SINGLE_CHAR_TERMINALS,
+    )
+    .unwrap();
+    assert_eq!(format_bool_expr(parsed), "(a & b) & c");
+
+    // Keywords are still recognised as whole words under `SINGLE_CHAR_TERMINALS`, rather than
+    // being split into single-character terminals themselves
+    let parsed = parse_bool_expr_str_with_options(
+        "a | true",
+        &["a"],
+        100,
+        Restrictions::SINGLE_CHAR_TERMINALS,
+    )
+    .unwrap();
+    assert_eq!(format_bool_expr(parsed), "true");
+
+    // `CASE_SENSITIVE` preserves the case of terminal names instead of lower-casing them
+    let parsed =
+        parse_bool_expr_str_with_options("MyVar", &["MyVar"], 100, Restrictions::CASE_SENSITIVE)
+            .unwrap();
+    assert_eq!(format_bool_expr(parsed), "MyVar");
+    assert!(
+        parse_bool_expr_str_with_options("MyVar", &["myvar"], 100, Restrictions::CASE_SENSITIVE)
+            .is_err()
+    );
+}
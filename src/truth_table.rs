@@ -0,0 +1,140 @@
+use std::collections::HashMap;
+
+use boolean_expression::Expr;
+use thiserror::Error;
+
+use super::format::format_bool_expr;
+
+/// The number of rows in a truth table doubles with every terminal, so this bounds `terminals` to
+/// keep a caller-supplied list from demanding billions of rows (or overflowing `1usize <<
+/// terminals.len()` outright once it reaches the platform's pointer width) - the same concern
+/// `max_nesting` addresses for parsing.
+const MAX_TERMINALS: usize = 24;
+
+/// An error produced while generating a [`TruthTable`].
+#[derive(Error, Debug)]
+pub enum TruthTableError {
+    #[error("expression references terminal '{terminal}', which was not included in the given terminals list")]
+    MissingTerminal { terminal: String },
+    #[error("{count} terminals given, which would require enumerating 2^{count} rows; the maximum is {MAX_TERMINALS} (this is a security measure)")]
+    TooManyTerminals { count: usize },
+}
+
+/// The full enumeration of a boolean expression's output over every possible assignment of its
+/// terminals, as produced by [`truth_table`].
+pub struct TruthTable {
+    /// The terminals this table was generated for, in column order.
+    pub terminals: Vec<String>,
+    /// The expression this table was generated from (kept around so it can be rendered in
+    /// [`format_truth_table`]).
+    pub expr: Expr<String>,
+    /// One row per assignment of `terminals`, in ascending order of the binary number they form.
+    pub rows: Vec<TruthTableRow>,
+}
+
+/// A single row of a [`TruthTable`]: an assignment of every terminal, and the boolean the
+/// expression evaluates to under that assignment.
+pub struct TruthTableRow {
+    /// The value of each terminal, in the same order as [`TruthTable::terminals`].
+    pub assignment: Vec<bool>,
+    /// The result of evaluating the expression under `assignment`.
+    pub result: bool,
+}
+
+/// Enumerates every assignment of the given terminals and evaluates `expr` under each one.
+///
+/// `terminals` is used verbatim as the column order (bit `i` of each row's index gives the value
+/// of `terminals[i]`), so callers wanting a deterministic layout should pass them pre-sorted.
+///
+/// Fails if `expr` references a terminal that isn't in `terminals`, since there would otherwise be
+/// no assignment to evaluate it under, or if more than `MAX_TERMINALS` terminals are given, since
+/// the table's row count doubles with every one of them.
+pub fn truth_table(expr: &Expr<String>, terminals: &[&str]) -> Result<TruthTable, TruthTableError> {
+    if terminals.len() > MAX_TERMINALS {
+        return Err(TruthTableError::TooManyTerminals {
+            count: terminals.len(),
+        });
+    }
+
+    let terminals: Vec<String> = terminals.iter().map(|t| t.to_string()).collect();
+    check_terminals(expr, &terminals)?;
+
+    let num_rows = 1usize << terminals.len();
+
+    let mut rows = Vec::with_capacity(num_rows);
+    for row in 0..num_rows {
+        let assignment: Vec<bool> = (0..terminals.len()).map(|i| (row >> i) & 1 == 1).collect();
+
+        let vars: HashMap<String, bool> = terminals
+            .iter()
+            .cloned()
+            .zip(assignment.iter().copied())
+            .collect();
+        let result = eval(expr, &vars);
+
+        rows.push(TruthTableRow { assignment, result });
+    }
+
+    Ok(TruthTable {
+        terminals,
+        expr: expr.clone(),
+        rows,
+    })
+}
+
+/// Checks that every terminal referenced by `expr` is present in `terminals`, so that [`eval`]
+/// can later look each one up without risk of a missing-key panic.
+fn check_terminals(expr: &Expr<String>, terminals: &[String]) -> Result<(), TruthTableError> {
+    match expr {
+        Expr::Terminal(name) => {
+            if terminals.contains(name) {
+                Ok(())
+            } else {
+                Err(TruthTableError::MissingTerminal {
+                    terminal: name.clone(),
+                })
+            }
+        }
+        Expr::Const(_) => Ok(()),
+        Expr::Not(inner) => check_terminals(inner, terminals),
+        Expr::And(lhs, rhs) | Expr::Or(lhs, rhs) => {
+            check_terminals(lhs, terminals)?;
+            check_terminals(rhs, terminals)
+        }
+    }
+}
+
+/// Recursively evaluates the given expression under the given assignment of terminals to
+/// boolean values.
+fn eval(expr: &Expr<String>, vars: &HashMap<String, bool>) -> bool {
+    match expr {
+        Expr::Terminal(name) => vars[name],
+        Expr::Const(value) => *value,
+        Expr::Not(inner) => !eval(inner, vars),
+        Expr::And(lhs, rhs) => eval(lhs, vars) && eval(rhs, vars),
+        Expr::Or(lhs, rhs) => eval(lhs, vars) || eval(rhs, vars),
+    }
+}
+
+/// Renders a [`TruthTable`] in the classic layout: a header of the terminal names followed by
+/// the formatted expression, then one `0`/`1` row per assignment.
+pub fn format_truth_table(table: &TruthTable) -> String {
+    let mut out = String::new();
+    for terminal in &table.terminals {
+        out.push_str(terminal);
+        out.push_str(" | ");
+    }
+    out.push_str(&format_bool_expr(table.expr.clone()));
+    out.push('\n');
+
+    for row in &table.rows {
+        for value in &row.assignment {
+            out.push_str(if *value { "1" } else { "0" });
+            out.push_str(" | ");
+        }
+        out.push_str(if row.result { "1" } else { "0" });
+        out.push('\n');
+    }
+
+    out
+}